@@ -42,6 +42,7 @@ impl Serialize for SingleSubstInternal {
 }
 
 #[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A single substitution subtable.
 pub struct SingleSubst {
     /// The mapping of input glyph IDs to replacement glyph IDs.
@@ -234,4 +235,15 @@ mod tests {
             panic!("Wrong format!");
         }
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_single_subst_serde_json_roundtrip() {
+        let subst = SingleSubst {
+            mapping: btreemap!(34 => 66, 35 => 66, 36 => 66),
+        };
+        let json = serde_json::to_string(&subst).unwrap();
+        let de: SingleSubst = serde_json::from_str(&json).unwrap();
+        assert_eq!(de, subst);
+    }
 }